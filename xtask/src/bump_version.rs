@@ -4,58 +4,78 @@ use crate::{
     out, utils,
 };
 use cargo_metadata::semver::Version;
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::io::{Read, Write};
-use std::path::Path;
-use std::{fs, path::PathBuf};
-
-lazy_static! {
-    /// Regular expression for replacing the version in the root package's Cargo.toml file.
-    static ref REPLACE_LOCO_LIB_VERSION_: Regex = Regex::new(
-        r#"(?P<name>name\s*=\s*".+\s+version\s*=\s*")(?P<version>[0-9]+\.[0-9]+\.[0-9]+)"#
-    )
-    .unwrap();
-
-    /// Regular expression for updating the version in loco-rs package dependencies in Cargo.toml files.
-    static ref REPLACE_LOCO_PACKAGE_VERSION: Regex =
-        Regex::new(r#"loco-rs = \{ (version|path) = "[^"]+""#).unwrap();
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, InlineTable, Item, TableLike, Value};
 
+/// Dependency tables, other than `[workspace.dependencies]`, that may carry a `loco-rs` entry.
+const DEPENDENCY_TABLE_NAMES: [&str; 3] =
+    ["dependencies", "dev-dependencies", "build-dependencies"];
+/// Base URL of the crates.io sparse index, see cargo's registry index format documentation.
+const CRATES_IO_SPARSE_INDEX: &str = "https://index.crates.io";
+
+/// What a `loco-rs` dependency entry should be rewritten to.
+pub enum LocoDependency<'a> {
+    /// `loco-rs = { path = "..." }`; a bare string shorthand entry is turned into an inline table.
+    Path(&'a str),
+    /// `loco-rs = { version = "..." }`, or the bare string form for shorthand entries.
+    Version(&'a Version),
+}
+
+/// One line of a crate's crates.io sparse index file.
+#[derive(Deserialize)]
+struct RegistryEntry {
+    vers: Version,
+    yanked: bool,
 }
 
 /// Bump all necessary loco resources with the given version.
 ///
+/// Unless `skip_registry_check` is set, `new_version` is verified against crates.io first: it
+/// must either already be published and not yanked (a post-publish bump of the starters) or be
+/// strictly newer than the latest published, non-yanked `loco-rs` version (a pre-publish bump). Pass
+/// `skip_registry_check` for air-gapped/offline runs.
+///
 /// # Errors
-/// Returns an error when it could not update one of the resources.
-pub fn bump(base_dir: &Path, new_version: &Version) -> Result<()> {
+/// Returns an error when it could not update one of the resources, or when the registry
+/// pre-flight check fails.
+pub fn bump(base_dir: &Path, new_version: &Version, skip_registry_check: bool) -> Result<()> {
+    if skip_registry_check {
+        println!("Skipping crates.io registry check for loco-rs {new_version}");
+    } else {
+        verify_publishable(new_version)?;
+    }
+
     root_package(base_dir, new_version)?;
     println!("Bump Loco lib updated successfully");
     update_starters(
         base_dir,
-        "loco-rs = { path = \"../../\"",
-        Some("loco-rs = { path = \"../../../\""),
+        &LocoDependency::Path("../../"),
+        Some(&LocoDependency::Path("../../../")),
     )?;
 
     println!("Testing starters CI");
-    let starter_projects: Vec<ci::RunResults> =
-        ci::inner_folders(&base_dir.join(utils::FOLDER_STARTERS))?;
+    let starter_projects: Vec<ci::RunResults> = ci::inner_folders(
+        &base_dir.join(utils::FOLDER_STARTERS),
+        ci::RunOptions::default(),
+    )?;
 
     println!("Starters CI results:");
     println!("{}", out::ci_results(&starter_projects));
-    for starter in &starter_projects {
-        if !starter.is_valid() {
-            return Err(Error::Message(format!(
-                "starter {} ins not passing the CI",
-                starter.path.display()
-            )));
-        }
+    let failing: Vec<String> = starter_projects
+        .iter()
+        .filter(|starter| !starter.is_valid())
+        .map(|starter| starter.path.display().to_string())
+        .collect();
+    if !failing.is_empty() {
+        return Err(Error::Message(format!(
+            "starters not passing the CI: {}",
+            failing.join(", ")
+        )));
     }
 
-    update_starters(
-        base_dir,
-        &format!("loco-rs = {{ version = \"{new_version}\""),
-        None,
-    )?;
+    update_starters(base_dir, &LocoDependency::Version(new_version), None)?;
     println!("Bump loco starters finished successfully");
 
     Ok(())
@@ -64,49 +84,51 @@ pub fn bump(base_dir: &Path, new_version: &Version) -> Result<()> {
 /// Bump the version of the loco library in the root package's Cargo.toml file.
 ///
 /// # Errors
-/// Returns an error when it could not parse the loco Cargo.toml file or has an error updating the file.
+/// Returns an error when it could not parse the loco Cargo.toml file or has an error updating
+/// the file.
 pub fn root_package(base_dir: &Path, new_version: &Version) -> Result<()> {
-    let mut content = String::new();
-
     let cargo_toml_file = base_dir.join("Cargo.toml");
-    fs::File::open(&cargo_toml_file)?.read_to_string(&mut content)?;
+    let mut doc = parse_manifest(&cargo_toml_file)?;
+
+    let version_item = doc
+        .get_mut("package")
+        .and_then(Item::as_table_like_mut)
+        .and_then(|package| package.get_mut("version"));
 
-    if !REPLACE_LOCO_LIB_VERSION_.is_match(&content) {
+    let Some(version_item) = version_item else {
         return Err(Error::BumpVersion {
             path: cargo_toml_file,
             package: "root_package".to_string(),
         });
-    }
+    };
 
-    let content = REPLACE_LOCO_LIB_VERSION_.replace(&content, |captures: &regex::Captures| {
-        format!("{}{}", &captures["name"], new_version)
-    });
-
-    let mut modified_file = fs::File::create(cargo_toml_file)?;
-    modified_file.write_all(content.as_bytes())?;
+    *version_item = Item::Value(Value::from(new_version.to_string()));
 
+    fs::write(&cargo_toml_file, doc.to_string())?;
     Ok(())
 }
 
-/// Update the dependencies of loco-rs in all starter projects to the given version.
+/// Update the `loco-rs` dependency in all starter projects (and their `migration` crate, if
+/// present) to the given dependency spec.
 ///
 /// # Errors
-/// Returns an error when it could not parse a loco Cargo.toml file or has an error updating the file.
+/// Returns an error when it could not parse a loco Cargo.toml file or has an error updating
+/// the file.
 pub fn update_starters(
     base_dir: &Path,
-    replace_with: &str,
-    replace_migrator: Option<&str>,
+    dependency: &LocoDependency<'_>,
+    migrator_dependency: Option<&LocoDependency<'_>>,
 ) -> Result<()> {
     let starter_projects = utils::get_cargo_folders(&base_dir.join(utils::FOLDER_STARTERS))?;
 
     for starter_project in starter_projects {
-        replace_loco_rs_version(&starter_project, replace_with)?;
+        replace_loco_rs_version(&starter_project, dependency)?;
 
         let migration_lock_file = starter_project.join("migration");
         if migration_lock_file.exists() {
             replace_loco_rs_version(
                 &migration_lock_file,
-                replace_migrator.unwrap_or(replace_with),
+                migrator_dependency.unwrap_or(dependency),
             )?;
         }
     }
@@ -114,24 +136,342 @@ pub fn update_starters(
     Ok(())
 }
 
-fn replace_loco_rs_version(path: &Path, replace_with: &str) -> Result<()> {
-    let mut content = String::new();
+/// Rewrite the `loco-rs` dependency entry in `path`'s Cargo.toml to `dependency`, wherever it
+/// appears (`[dependencies]`, `[dev-dependencies]`, `[build-dependencies]`, their target-specific
+/// counterparts, or `[workspace.dependencies]`), preserving every other key, comment and the
+/// surrounding formatting.
+fn replace_loco_rs_version(path: &Path, dependency: &LocoDependency<'_>) -> Result<()> {
     let cargo_toml_file = path.join("Cargo.toml");
-    fs::File::open(&cargo_toml_file)?.read_to_string(&mut content)?;
+    let mut doc = parse_manifest(&cargo_toml_file)?;
 
-    if !REPLACE_LOCO_PACKAGE_VERSION.is_match(&content) {
+    if !set_loco_rs_in_document(&mut doc, dependency) {
         return Err(Error::BumpVersion {
             path: cargo_toml_file,
             package: "loco-rs".to_string(),
         });
     }
-    content = REPLACE_LOCO_PACKAGE_VERSION
-        .replace_all(&content, |_captures: &regex::Captures| {
-            replace_with.to_string()
-        })
-        .to_string();
 
-    let mut modified_file = fs::File::create(cargo_toml_file)?;
-    modified_file.write_all(content.as_bytes())?;
+    fs::write(&cargo_toml_file, doc.to_string())?;
     Ok(())
 }
+
+fn parse_manifest(cargo_toml_file: &PathBuf) -> Result<DocumentMut> {
+    let content = fs::read_to_string(cargo_toml_file)?;
+    content
+        .parse::<DocumentMut>()
+        .map_err(|err| Error::Message(format!("{}: {err}", cargo_toml_file.display())))
+}
+
+/// Find and rewrite every `loco-rs` dependency entry in `doc`, returning whether at least one
+/// was found.
+fn set_loco_rs_in_document(doc: &mut DocumentMut, dependency: &LocoDependency<'_>) -> bool {
+    let mut updated = false;
+
+    for table_name in DEPENDENCY_TABLE_NAMES {
+        if let Some(table) = doc.get_mut(table_name).and_then(Item::as_table_like_mut) {
+            updated |= set_loco_rs_in_table(table, dependency);
+        }
+    }
+
+    if let Some(workspace_deps) = doc
+        .get_mut("workspace")
+        .and_then(Item::as_table_like_mut)
+        .and_then(|workspace| workspace.get_mut("dependencies"))
+        .and_then(Item::as_table_like_mut)
+    {
+        updated |= set_loco_rs_in_table(workspace_deps, dependency);
+    }
+
+    if let Some(targets) = doc.get_mut("target").and_then(Item::as_table_like_mut) {
+        for (_, target) in targets.iter_mut() {
+            let Some(target_table) = target.as_table_like_mut() else {
+                continue;
+            };
+            for table_name in DEPENDENCY_TABLE_NAMES {
+                if let Some(table) = target_table
+                    .get_mut(table_name)
+                    .and_then(Item::as_table_like_mut)
+                {
+                    updated |= set_loco_rs_in_table(table, dependency);
+                }
+            }
+        }
+    }
+
+    updated
+}
+
+/// Rewrite the `loco-rs` entry in `table`, if present, to `dependency`. Leaves a bare string
+/// entry (`loco-rs = "1.2.3"`) as a string when only the version changes, and only converts to
+/// an inline table when a `path` needs to be expressed; an existing inline table, or a
+/// `[dependencies.loco-rs]` style table, keeps every key other than `version`/`path` untouched
+/// (e.g. `features`, `default-features`).
+fn set_loco_rs_in_table(table: &mut dyn TableLike, dependency: &LocoDependency<'_>) -> bool {
+    let Some(item) = table.get_mut("loco-rs") else {
+        return false;
+    };
+
+    match item {
+        Item::Value(Value::String(_)) => match dependency {
+            LocoDependency::Version(version) => {
+                *item = Item::Value(Value::from(version.to_string()));
+            }
+            LocoDependency::Path(path) => {
+                let mut inline = InlineTable::new();
+                inline.insert("path", Value::from(*path));
+                *item = Item::Value(Value::InlineTable(inline));
+            }
+        },
+        Item::Value(Value::InlineTable(inline)) => {
+            inline.remove("version");
+            inline.remove("path");
+            match dependency {
+                LocoDependency::Version(version) => {
+                    inline.insert("version", Value::from(version.to_string()));
+                }
+                LocoDependency::Path(path) => {
+                    inline.insert("path", Value::from(*path));
+                }
+            }
+        }
+        Item::Table(dep_table) => {
+            dep_table.remove("version");
+            dep_table.remove("path");
+            match dependency {
+                LocoDependency::Version(version) => {
+                    dep_table.insert("version", Item::Value(Value::from(version.to_string())));
+                }
+                LocoDependency::Path(path) => {
+                    dep_table.insert("path", Item::Value(Value::from(*path)));
+                }
+            }
+        }
+        _ => return false,
+    }
+
+    true
+}
+
+/// Verify that `new_version` is safe to depend on for `loco-rs`: either it is already published
+/// on crates.io and not yanked (a post-publish version bump of the starters), or it is strictly
+/// newer than the latest published, non-yanked version (a pre-publish version bump).
+///
+/// # Errors
+/// Returns an error when the registry can't be queried, when `new_version` is published but
+/// yanked, when it is neither published nor an advance on the latest published version, or when
+/// no non-yanked version is published at all.
+fn verify_publishable(new_version: &Version) -> Result<()> {
+    let entries = fetch_registry_entries("loco-rs")?;
+
+    if let Some(published) = entries.iter().find(|entry| &entry.vers == new_version) {
+        return if published.yanked {
+            Err(Error::Message(format!(
+                "loco-rs {new_version} is published on crates.io but has been yanked"
+            )))
+        } else {
+            Ok(())
+        };
+    }
+
+    match entries
+        .iter()
+        .filter(|entry| !entry.yanked)
+        .map(|entry| &entry.vers)
+        .max()
+    {
+        Some(latest) if new_version > latest => Ok(()),
+        Some(latest) => Err(Error::Message(format!(
+            "loco-rs {new_version} is not greater than the latest published version {latest}"
+        ))),
+        None => Err(Error::Message(
+            "could not find any published, non-yanked versions of loco-rs on crates.io".to_string(),
+        )),
+    }
+}
+
+/// Fetch and parse every version entry of `crate_name` from the crates.io sparse index.
+///
+/// # Errors
+/// Returns an error when the index can't be reached or a line fails to parse.
+fn fetch_registry_entries(crate_name: &str) -> Result<Vec<RegistryEntry>> {
+    let url = sparse_index_url(crate_name);
+    let body = ureq::get(&url).call().map_err(|err| {
+        Error::Message(format!(
+            "failed to query the crates.io index for {crate_name} at {url}: {err}"
+        ))
+    })?;
+    let body = body.into_string().map_err(|err| {
+        Error::Message(format!(
+            "failed to read crates.io index response for {crate_name}: {err}"
+        ))
+    })?;
+
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            serde_json::from_str(line).map_err(|err| {
+                Error::Message(format!(
+                    "failed to parse crates.io index entry for {crate_name}: {err}"
+                ))
+            })
+        })
+        .collect()
+}
+
+/// Build the sparse index URL for `crate_name`, following cargo's index layout (1/2/3-letter
+/// names get their own tiers, everything else is nested under its first two and next two
+/// characters).
+fn sparse_index_url(crate_name: &str) -> String {
+    let lower = crate_name.to_lowercase();
+    let path = match lower.len() {
+        1 => format!("1/{lower}"),
+        2 => format!("2/{lower}"),
+        3 => format!("3/{}/{lower}", &lower[..1]),
+        _ => format!("{}/{}/{lower}", &lower[..2], &lower[2..4]),
+    };
+    format!("{CRATES_IO_SPARSE_INDEX}/{path}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(raw: &str) -> Version {
+        Version::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn bare_string_dependency_updates_in_place() {
+        let mut doc = "[dependencies]\nloco-rs = \"0.1.0\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        assert!(set_loco_rs_in_document(
+            &mut doc,
+            &LocoDependency::Version(&version("0.2.0"))
+        ));
+        assert_eq!(doc["dependencies"]["loco-rs"].as_str(), Some("0.2.0"));
+    }
+
+    #[test]
+    fn bare_string_dependency_becomes_inline_table_for_a_path() {
+        let mut doc = "[dependencies]\nloco-rs = \"0.1.0\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        assert!(set_loco_rs_in_document(
+            &mut doc,
+            &LocoDependency::Path("../../")
+        ));
+        assert_eq!(
+            doc["dependencies"]["loco-rs"]["path"].as_str(),
+            Some("../../")
+        );
+    }
+
+    #[test]
+    fn inline_table_keeps_unrelated_keys_when_version_changes() {
+        let mut doc = "[dependencies]\nloco-rs = { version = \"0.1.0\", features = [\"cli\"] }\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        assert!(set_loco_rs_in_document(
+            &mut doc,
+            &LocoDependency::Version(&version("0.2.0"))
+        ));
+
+        let table = doc["dependencies"]["loco-rs"].as_inline_table().unwrap();
+        assert_eq!(table.get("version").and_then(Value::as_str), Some("0.2.0"));
+        assert!(table.get("path").is_none());
+        assert_eq!(
+            table
+                .get("features")
+                .and_then(Value::as_array)
+                .map(|a| a.len()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn inline_table_switches_from_version_to_path() {
+        let mut doc = "[dependencies]\nloco-rs = { version = \"0.1.0\" }\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        assert!(set_loco_rs_in_document(
+            &mut doc,
+            &LocoDependency::Path("../../")
+        ));
+
+        let table = doc["dependencies"]["loco-rs"].as_inline_table().unwrap();
+        assert_eq!(table.get("path").and_then(Value::as_str), Some("../../"));
+        assert!(table.get("version").is_none());
+    }
+
+    #[test]
+    fn updates_workspace_dependencies_table() {
+        let mut doc = "[workspace.dependencies]\nloco-rs = { version = \"0.1.0\" }\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        assert!(set_loco_rs_in_document(
+            &mut doc,
+            &LocoDependency::Version(&version("0.2.0"))
+        ));
+        assert_eq!(
+            doc["workspace"]["dependencies"]["loco-rs"]["version"].as_str(),
+            Some("0.2.0")
+        );
+    }
+
+    #[test]
+    fn updates_target_specific_dependency_tables() {
+        let mut doc = "[target.'cfg(windows)'.dependencies]\nloco-rs = { path = \"../../\" }\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        assert!(set_loco_rs_in_document(
+            &mut doc,
+            &LocoDependency::Version(&version("0.2.0"))
+        ));
+        assert_eq!(
+            doc["target"]["cfg(windows)"]["dependencies"]["loco-rs"]["version"].as_str(),
+            Some("0.2.0")
+        );
+    }
+
+    #[test]
+    fn missing_dependency_is_reported_as_not_found() {
+        let mut doc = "[dependencies]\nserde = \"1.0\"\n"
+            .parse::<DocumentMut>()
+            .unwrap();
+
+        assert!(!set_loco_rs_in_document(
+            &mut doc,
+            &LocoDependency::Version(&version("0.2.0"))
+        ));
+    }
+
+    #[test]
+    fn tiers_one_two_and_three_letter_crate_names() {
+        assert_eq!(sparse_index_url("a"), "https://index.crates.io/1/a");
+        assert_eq!(sparse_index_url("ab"), "https://index.crates.io/2/ab");
+        assert_eq!(sparse_index_url("abc"), "https://index.crates.io/3/a/abc");
+    }
+
+    #[test]
+    fn tiers_longer_crate_names_under_their_first_four_characters() {
+        assert_eq!(
+            sparse_index_url("loco-rs"),
+            "https://index.crates.io/lo/co/loco-rs"
+        );
+    }
+
+    #[test]
+    fn lowercases_the_crate_name_before_tiering() {
+        assert_eq!(
+            sparse_index_url("Loco-RS"),
+            "https://index.crates.io/lo/co/loco-rs"
+        );
+    }
+}
@@ -1,14 +1,23 @@
-use crate::errors::Result;
+use crate::errors::{Error, Result};
 use crate::utils;
 use duct::cmd;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::Output;
+use std::sync::Mutex;
+use std::thread;
+use std::time::UNIX_EPOCH;
 
-const FMT_TEST: [&str; 3] = ["test", "--all-features", "--all"];
+const FMT_TEST: [&str; 4] = ["test", "--all-features", "--all", "--message-format=json"];
 const FMT_ARGS: [&str; 4] = ["fmt", "--all", "--", "--check"];
-const FMT_CLIPPY: [&str; 8] = [
+const FMT_CLIPPY: [&str; 9] = [
     "clippy",
+    "--message-format=json",
     "--",
     "-W",
     "clippy::pedantic",
@@ -17,6 +26,29 @@ const FMT_CLIPPY: [&str; 8] = [
     "-W",
     "rust-2018-idioms",
 ];
+const CHECK_ARGS: [&str; 3] = ["check", "--all-features", "--all"];
+const FIX_FMT_ARGS: [&str; 2] = ["fmt", "--all"];
+const FIX_CLIPPY_ARGS: [&str; 4] = ["clippy", "--fix", "--allow-dirty", "--allow-staged"];
+const FIX_EDITION_ARGS: [&str; 4] = ["fix", "--edition-idioms", "--allow-dirty", "--allow-staged"];
+/// Path, relative to a cargo folder, where its freshness fingerprint is persisted.
+const FINGERPRINT_FILE: &str = "target/.loco-ci-fingerprint";
+/// Subfolders whose `*.rs` files are tracked for the freshness fingerprint.
+const TRACKED_RS_DIRS: [&str; 3] = ["src", "tests", "migration"];
+/// Top-level manifest files tracked for the freshness fingerprint.
+const TRACKED_MANIFEST_FILES: [&str; 2] = ["Cargo.toml", "Cargo.lock"];
+
+#[derive(Default, Clone, Copy, Debug)]
+pub struct RunOptions {
+    /// Apply automatic fixes before checking, see [`run`]. Implies `force`-like behavior: the
+    /// freshness cache is always bypassed so a fix run never gets skipped as `[FRESH]`.
+    pub fix: bool,
+    /// Ignore the freshness fingerprint cache and always rerun, see [`run`].
+    pub force: bool,
+    /// Cap on concurrently running folders in [`inner_folders`], `None` meaning "use
+    /// `std::thread::available_parallelism`". `Some(1)` runs folders sequentially, which keeps
+    /// output deterministic.
+    pub jobs: Option<usize>,
+}
 
 #[derive(Default, Debug)]
 pub struct RunResults {
@@ -24,6 +56,21 @@ pub struct RunResults {
     pub fmt: bool,
     pub clippy: bool,
     pub test: bool,
+    /// Number of fix commands (fmt/clippy/edition) that succeeded and actually changed a
+    /// tracked file, also printed as a `[FIXED n]` line per folder while `options.fix` is set.
+    ///
+    /// Always `0` when `run`/`inner_folders` was called outside of fix mode.
+    pub fixes_applied: usize,
+    /// Whether this result was served from the freshness fingerprint cache without
+    /// rerunning `cargo fmt`/`clippy`/`test`.
+    pub fresh: bool,
+    pub fmt_output: CapturedOutput,
+    pub clippy_output: CapturedOutput,
+    pub test_output: CapturedOutput,
+    /// Compiler/clippy diagnostics parsed from `clippy_output`'s `--message-format=json` stream.
+    pub clippy_diagnostics: Vec<Diagnostic>,
+    /// Compiler diagnostics parsed from `test_output`'s `--message-format=json` stream.
+    pub test_diagnostics: Vec<Diagnostic>,
 }
 
 impl RunResults {
@@ -33,17 +80,93 @@ impl RunResults {
     }
 }
 
+/// Captured stdout/stderr and exit status of a single cargo invocation.
+#[derive(Default, Debug, Serialize)]
+pub struct CapturedOutput {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CapturedOutput {
+    fn from_output(output: &Output) -> Self {
+        Self {
+            success: output.status.success(),
+            exit_code: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+
+    /// A result standing in for a stage that didn't actually run (e.g. a `[FRESH]` skip).
+    fn skipped() -> Self {
+        Self {
+            success: true,
+            ..Self::default()
+        }
+    }
+}
+
+/// A single rustc/clippy diagnostic parsed out of a `--message-format=json` stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub level: String,
+    pub message: String,
+    pub spans: Vec<DiagnosticSpan>,
+}
+
+/// The source location a [`Diagnostic`] points at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticSpan {
+    pub file_name: String,
+    pub line_start: usize,
+    pub column_start: usize,
+}
+
+/// One line of cargo's `--message-format=json` output; only `compiler-message` lines carry a
+/// [`Diagnostic`], every other reason (`compiler-artifact`, `build-finished`, ...) is skipped.
+#[derive(Debug, Deserialize)]
+struct CargoMessage {
+    reason: String,
+    #[serde(default)]
+    message: Option<Diagnostic>,
+}
+
+/// Parse the `compiler-message` diagnostics out of a `--message-format=json` stdout stream,
+/// silently skipping lines that aren't JSON (e.g. the plain-text test harness output
+/// `cargo test` interleaves alongside the build's JSON messages).
+fn parse_diagnostics(stdout: &str) -> Vec<Diagnostic> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CargoMessage>(line).ok())
+        .filter(|cargo_message| cargo_message.reason == "compiler-message")
+        .filter_map(|cargo_message| cargo_message.message)
+        .collect()
+}
+
 /// Run CI on all Loco resources (lib, cli, starters, examples, etc.).
 ///
+/// See [`run`] for what `options.fix` and `options.force` do.
+///
 /// # Errors
 /// when could not run ci on the given resource
 ///
-pub fn all_resources(base_dir: &Path) -> Result<Vec<RunResults>> {
+pub fn all_resources(base_dir: &Path, options: RunOptions) -> Result<Vec<RunResults>> {
     let mut result = vec![];
-    result.push(run(base_dir).expect("loco lib mast be tested"));
-    result.extend(inner_folders(&base_dir.join(utils::FOLDER_EXAMPLES))?);
-    result.extend(inner_folders(&base_dir.join(utils::FOLDER_STARTERS))?);
-    result.extend(inner_folders(&base_dir.join(utils::FOLDER_LOCO_CLI))?);
+    result.push(run(base_dir, options).expect("loco lib mast be tested"));
+    result.extend(inner_folders(
+        &base_dir.join(utils::FOLDER_EXAMPLES),
+        options,
+    )?);
+    result.extend(inner_folders(
+        &base_dir.join(utils::FOLDER_STARTERS),
+        options,
+    )?);
+    result.extend(inner_folders(
+        &base_dir.join(utils::FOLDER_LOCO_CLI),
+        options,
+    )?);
 
     Ok(result)
 }
@@ -52,63 +175,561 @@ pub fn all_resources(base_dir: &Path) -> Result<Vec<RunResults>> {
 ///
 /// For example, run CI on all examples/starters folders dynamically by selecting the first root folder and running CI one level down.
 ///
+/// Folders are dispatched across a worker pool sized to `options.jobs` (or the available core
+/// count when unset), each folder printing a `[START]`/`[DONE]`/`[FAILED]` line as it runs so
+/// output stays legible despite the interleaving. `options.jobs = Some(1)` disables the pool and
+/// runs folders one at a time for deterministic logs. See [`run`] for what `options.fix` and
+/// `options.force` do.
+///
 /// # Errors
 /// when could not get cargo folders
-pub fn inner_folders(root_folder: &Path) -> Result<Vec<RunResults>> {
+pub fn inner_folders(root_folder: &Path, options: RunOptions) -> Result<Vec<RunResults>> {
     let cargo_projects = utils::get_cargo_folders(root_folder)?;
-    let mut results = vec![];
+    Ok(run_many(cargo_projects, options))
+}
+
+/// Run [`run`] over `projects`, in parallel unless `options.jobs == Some(1)`. See
+/// [`inner_folders`] for the concurrency/logging behavior.
+fn run_many(projects: Vec<PathBuf>, options: RunOptions) -> Vec<RunResults> {
+    let jobs = options.jobs.unwrap_or_else(default_jobs).max(1);
+    if jobs == 1 {
+        return projects
+            .into_iter()
+            .filter_map(|project| run_logged(&project, options))
+            .collect();
+    }
+
+    let queue = Mutex::new(projects.into_iter());
+    let results = Mutex::new(Vec::new());
 
-    for project in cargo_projects {
-        if let Some(res) = run(&project) {
-            results.push(res);
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                while let Some(project) = queue.lock().expect("ci job queue poisoned").next() {
+                    if let Some(result) = run_logged(&project, options) {
+                        results.lock().expect("ci results poisoned").push(result);
+                    }
+                }
+            });
         }
+    });
+
+    results.into_inner().expect("ci results poisoned")
+}
+
+/// Run [`run`] on `project`, printing the same `[START]`/`[DONE]`/`[FAILED]` lines regardless of
+/// whether [`run_many`] takes its sequential or parallel path, so `--jobs 1` (which `default_jobs`
+/// also returns on single-core machines) stays just as legible as the parallel path.
+fn run_logged(project: &Path, options: RunOptions) -> Option<RunResults> {
+    println!("[START] {}", project.display());
+    let result = run(project, options);
+    if let Some(result) = &result {
+        println!(
+            "[{}] {}",
+            if result.is_valid() { "DONE" } else { "FAILED" },
+            project.display()
+        );
     }
-    Ok(results)
+    result
+}
+
+/// Default worker pool size: the number of available CPU cores, falling back to `1`.
+fn default_jobs() -> usize {
+    thread::available_parallelism()
+        .map(NonZeroUsize::get)
+        .unwrap_or(1)
 }
 
 /// Run the entire CI flow on the given folder path.
 ///
+/// Unless `options.force` or `options.fix` is set, a folder whose tracked files (`*.rs` under
+/// `src`/`tests`/`migration`, plus `Cargo.toml`/`Cargo.lock`) are unchanged since the last
+/// *valid* run is served from its `target/.loco-ci-fingerprint` cache as a `[FRESH]` result,
+/// skipping `cargo fmt`/`clippy`/`test` entirely. `options.fix` always forces a full rerun: a
+/// folder can be cached as valid while still carrying unfixed `-W` lint warnings (they don't
+/// affect `is_valid()`), and skipping the cache in fix mode is the only way to guarantee
+/// [`apply_fixes`] actually runs.
+///
+/// When `options.fix` is `true`, [`apply_fixes`] runs first so that `cargo fmt --all`,
+/// `cargo clippy --fix` and `cargo fix --edition-idioms` repair the folder in
+/// place, and the fmt/clippy/test results below reflect the repaired tree.
+///
 /// Returns `None` if it is not a Rust folder.
 #[must_use]
-pub fn run(dir: &Path) -> Option<RunResults> {
-    if dir.join("Cargo.toml").exists() {
-        Some(RunResults {
-            path: dir.to_path_buf(),
-            fmt: cargo_fmt(dir).is_ok(),
-            clippy: cargo_clippy(dir).is_ok(),
-            test: cargo_test(dir).is_ok(),
-        })
-    } else {
-        None
+pub fn run(dir: &Path, options: RunOptions) -> Option<RunResults> {
+    if !dir.join("Cargo.toml").exists() {
+        return None;
+    }
+
+    if !options.force && !options.fix {
+        if let Some(cached) = fresh_result(dir) {
+            return Some(cached);
+        }
+    }
+
+    let fixes_applied = if options.fix { apply_fixes(dir) } else { 0 };
+    if options.fix {
+        println!(
+            "[FIXED {fixes_applied}] {} ({fixes_applied} fix command(s) changed files)",
+            dir.display()
+        );
+    }
+
+    let fmt_output = cargo_fmt(dir);
+    let clippy_output = cargo_clippy(dir);
+    let test_output = cargo_test(dir);
+    let clippy_diagnostics = parse_diagnostics(&clippy_output.stdout);
+    let test_diagnostics = parse_diagnostics(&test_output.stdout);
+
+    let result = RunResults {
+        path: dir.to_path_buf(),
+        fmt: fmt_output.success,
+        clippy: clippy_output.success,
+        test: test_output.success,
+        fixes_applied,
+        fresh: false,
+        fmt_output,
+        clippy_output,
+        test_output,
+        clippy_diagnostics,
+        test_diagnostics,
+    };
+
+    // Only a valid run is trustworthy enough to skip next time.
+    if result.is_valid() {
+        if let Ok(fingerprint) = compute_fingerprint(dir) {
+            let _ = write_fingerprint(dir, &fingerprint);
+        }
+    }
+
+    Some(result)
+}
+
+/// Return a `[FRESH]` result if `dir`'s current fingerprint matches the one stored from its
+/// last valid run, `None` otherwise (forcing a full rerun).
+fn fresh_result(dir: &Path) -> Option<RunResults> {
+    let stored = fs::read_to_string(dir.join(FINGERPRINT_FILE)).ok()?;
+    let current = compute_fingerprint(dir).ok()?;
+    if stored != current {
+        return None;
+    }
+
+    println!(
+        "[FRESH] folder {} is unchanged, skipping cargo fmt/clippy/test",
+        dir.display()
+    );
+    Some(RunResults {
+        path: dir.to_path_buf(),
+        fmt: true,
+        clippy: true,
+        test: true,
+        fixes_applied: 0,
+        fresh: true,
+        fmt_output: CapturedOutput::skipped(),
+        clippy_output: CapturedOutput::skipped(),
+        test_output: CapturedOutput::skipped(),
+        clippy_diagnostics: Vec::new(),
+        test_diagnostics: Vec::new(),
+    })
+}
+
+/// Compute a fingerprint over `dir`'s tracked files, combining each file's relative path,
+/// length and mtime. Falls back to hashing file contents when mtime resolution is too coarse
+/// to tell successive edits apart, so a rename that changes the tracked file set (sorted
+/// relative paths are hashed too) always invalidates the fingerprint even if mtimes match.
+fn compute_fingerprint(dir: &Path) -> Result<String> {
+    let mut files = tracked_files(dir)?;
+    files.sort();
+
+    let mut hasher = DefaultHasher::new();
+    for file in &files {
+        file.strip_prefix(dir).unwrap_or(file).hash(&mut hasher);
+
+        let metadata = fs::metadata(file)?;
+        metadata.len().hash(&mut hasher);
+
+        let fine_mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+            .filter(|modified| modified.subsec_nanos() != 0);
+
+        match fine_mtime {
+            Some(modified) => modified.as_nanos().hash(&mut hasher),
+            None => fs::read(file)?.hash(&mut hasher),
+        }
+    }
+
+    Ok(format!("{:x}", hasher.finish()))
+}
+
+/// Collect the files tracked for `dir`'s freshness fingerprint: every `*.rs` file under
+/// `src`/`tests`/`migration`, plus `Cargo.toml` and `Cargo.lock` at the folder root.
+fn tracked_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    for rs_dir in TRACKED_RS_DIRS {
+        collect_rs_files(&dir.join(rs_dir), &mut files)?;
+    }
+    for manifest in TRACKED_MANIFEST_FILES {
+        let path = dir.join(manifest);
+        if path.exists() {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+/// Recursively collect `*.rs` files under `dir`, doing nothing if `dir` does not exist.
+fn collect_rs_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_rs_files(&path, out)?;
+        } else if path.extension().is_some_and(|ext| ext == "rs") {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Persist `dir`'s freshness fingerprint, creating `target/` if it does not exist yet.
+fn write_fingerprint(dir: &Path, fingerprint: &str) -> Result<()> {
+    let fingerprint_path = dir.join(FINGERPRINT_FILE);
+    if let Some(parent) = fingerprint_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(fingerprint_path, fingerprint)?;
+    Ok(())
+}
+
+/// Apply automatic fixes to the given folder and report how many of them actually changed a
+/// tracked file, rather than how many merely exited successfully (`cargo fmt --all` on an
+/// already-formatted folder exits `0` too, but changed nothing).
+///
+/// Mirrors rustfix's own safety rule: a folder that fails to compile is left untouched rather
+/// than risking fixes being layered on top of a broken build, so `cargo check` must succeed
+/// before `cargo fmt --all`, `cargo clippy --fix` and `cargo fix --edition-idioms` run.
+fn apply_fixes(dir: &Path) -> usize {
+    if cargo_check(dir).is_err() {
+        println!(
+            "Skipping fixes in folder {} because it does not compile",
+            dir.display()
+        );
+        return 0;
+    }
+
+    let mut fixes_applied = 0;
+    if run_fix_if_changed(dir, cargo_fmt_fix) {
+        fixes_applied += 1;
+    }
+    if run_fix_if_changed(dir, cargo_clippy_fix) {
+        fixes_applied += 1;
     }
+    if run_fix_if_changed(dir, cargo_fix_edition) {
+        fixes_applied += 1;
+    }
+    fixes_applied
 }
 
-/// Run cargo test on the given directory.
-fn cargo_test(dir: &Path) -> Result<Output> {
+/// Run a fix command and report whether it both succeeded and actually changed one of `dir`'s
+/// tracked files, comparing the freshness fingerprint from before and after the run.
+fn run_fix_if_changed(dir: &Path, fix: impl FnOnce(&Path) -> Result<Output>) -> bool {
+    let before = compute_fingerprint(dir).ok();
+    if fix(dir).is_err() {
+        return false;
+    }
+    compute_fingerprint(dir).ok() != before
+}
+
+/// Run cargo test on the given directory, capturing its stdout/stderr and exit status rather
+/// than only reporting pass/fail.
+fn cargo_test(dir: &Path) -> CapturedOutput {
     println!(
         "Running `cargo {}` in folder {}",
         FMT_TEST.join(" "),
         dir.display()
     );
-    Ok(cmd("cargo", FMT_TEST.as_slice()).dir(dir).run()?)
+    run_captured("cargo", FMT_TEST.as_slice(), dir)
 }
 
-/// Run cargo fmt on the given directory.
-fn cargo_fmt(dir: &Path) -> Result<Output> {
+/// Run cargo fmt on the given directory, capturing its stdout/stderr and exit status rather
+/// than only reporting pass/fail.
+fn cargo_fmt(dir: &Path) -> CapturedOutput {
     println!(
         "Running `cargo {}` in folder {}",
         FMT_ARGS.join(" "),
         dir.display()
     );
-    Ok(cmd("cargo", FMT_ARGS.as_slice()).dir(dir).run()?)
+    run_captured("cargo", FMT_ARGS.as_slice(), dir)
 }
 
-/// Run cargo clippy on the given directory.
-fn cargo_clippy(dir: &Path) -> Result<Output> {
+/// Run cargo clippy on the given directory, capturing its stdout/stderr and exit status rather
+/// than only reporting pass/fail.
+fn cargo_clippy(dir: &Path) -> CapturedOutput {
     println!(
         "Running `cargo {}` in folder {}",
         FMT_CLIPPY.join(" "),
         dir.display()
     );
-    Ok(cmd("cargo", FMT_CLIPPY.as_slice()).dir(dir).run()?)
+    run_captured("cargo", FMT_CLIPPY.as_slice(), dir)
+}
+
+/// Run `program` with `args` in `dir`, capturing stdout/stderr/exit status instead of failing on
+/// a non-zero exit so a lint/test failure still produces a [`CapturedOutput`] to report.
+fn run_captured(program: &str, args: &[&str], dir: &Path) -> CapturedOutput {
+    cmd(program, args)
+        .dir(dir)
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()
+        .map(|output| CapturedOutput::from_output(&output))
+        .unwrap_or_default()
+}
+
+/// Run cargo check on the given directory, used to gate fix application on a compiling build.
+fn cargo_check(dir: &Path) -> Result<Output> {
+    println!(
+        "Running `cargo {}` in folder {}",
+        CHECK_ARGS.join(" "),
+        dir.display()
+    );
+    Ok(cmd("cargo", CHECK_ARGS.as_slice()).dir(dir).run()?)
+}
+
+/// Run cargo fmt in write mode on the given directory.
+fn cargo_fmt_fix(dir: &Path) -> Result<Output> {
+    println!(
+        "Running `cargo {}` in folder {}",
+        FIX_FMT_ARGS.join(" "),
+        dir.display()
+    );
+    Ok(cmd("cargo", FIX_FMT_ARGS.as_slice()).dir(dir).run()?)
+}
+
+/// Run cargo clippy --fix on the given directory.
+fn cargo_clippy_fix(dir: &Path) -> Result<Output> {
+    println!(
+        "Running `cargo {}` in folder {}",
+        FIX_CLIPPY_ARGS.join(" "),
+        dir.display()
+    );
+    Ok(cmd("cargo", FIX_CLIPPY_ARGS.as_slice()).dir(dir).run()?)
+}
+
+/// Run cargo fix --edition-idioms on the given directory.
+fn cargo_fix_edition(dir: &Path) -> Result<Output> {
+    println!(
+        "Running `cargo {}` in folder {}",
+        FIX_EDITION_ARGS.join(" "),
+        dir.display()
+    );
+    Ok(cmd("cargo", FIX_EDITION_ARGS.as_slice()).dir(dir).run()?)
+}
+
+/// A single folder's entry in the JSON report written by [`export_json_report`].
+#[derive(Serialize)]
+struct JsonReportEntry<'a> {
+    path: String,
+    fmt: bool,
+    clippy: bool,
+    test: bool,
+    fresh: bool,
+    fixes_applied: usize,
+    fmt_output: &'a CapturedOutput,
+    clippy_output: &'a CapturedOutput,
+    test_output: &'a CapturedOutput,
+    clippy_diagnostics: &'a [Diagnostic],
+    test_diagnostics: &'a [Diagnostic],
+}
+
+impl<'a> From<&'a RunResults> for JsonReportEntry<'a> {
+    fn from(result: &'a RunResults) -> Self {
+        Self {
+            path: result.path.display().to_string(),
+            fmt: result.fmt,
+            clippy: result.clippy,
+            test: result.test,
+            fresh: result.fresh,
+            fixes_applied: result.fixes_applied,
+            fmt_output: &result.fmt_output,
+            clippy_output: &result.clippy_output,
+            test_output: &result.test_output,
+            clippy_diagnostics: &result.clippy_diagnostics,
+            test_diagnostics: &result.test_diagnostics,
+        }
+    }
+}
+
+/// Write a JSON summary of `results` to `path`, suitable for CI dashboards: one entry per
+/// folder carrying its pass/fail flags, the captured stdout/stderr of each stage, and the
+/// parsed clippy/test diagnostics that explain a failure, rather than the opaque booleans
+/// [`crate::out::ci_results`] prints.
+///
+/// # Errors
+/// Returns an error when the report could not be serialized or written.
+pub fn export_json_report(results: &[RunResults], path: &Path) -> Result<()> {
+    let report: Vec<JsonReportEntry> = results.iter().map(JsonReportEntry::from).collect();
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|err| Error::Message(format!("failed to serialize CI report: {err}")))?;
+    fs::write(path, json)?;
+    Ok(())
+}
+
+/// Write a JUnit XML report of `results` to `path`, suitable for CI dashboards that ingest test
+/// results (one `<testcase>` per folder, with a `<failure>` carrying the failing stage(s), their
+/// diagnostic count, and their captured stdout/stderr).
+///
+/// # Errors
+/// Returns an error when the report could not be written.
+pub fn export_junit_report(results: &[RunResults], path: &Path) -> Result<()> {
+    let failures = results.iter().filter(|result| !result.is_valid()).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites>\n<testsuite name=\"loco-ci\" tests=\"{}\" failures=\"{}\">\n",
+        results.len(),
+        failures
+    ));
+
+    for result in results {
+        xml.push_str(&format!(
+            "  <testcase classname=\"loco-ci\" name=\"{}\">\n",
+            xml_escape(&result.path.display().to_string())
+        ));
+        if !result.is_valid() {
+            let message = xml_escape(&failure_message(result));
+            let details = xml_escape(&failure_details(result));
+            xml.push_str(&format!(
+                "    <failure message=\"{message}\">{details}</failure>\n"
+            ));
+        }
+        xml.push_str("  </testcase>\n");
+    }
+
+    xml.push_str("</testsuite>\n</testsuites>\n");
+    fs::write(path, xml)?;
+    Ok(())
+}
+
+/// Summarize which stage(s) failed for `result`, for the JUnit `<failure>` message attribute.
+fn failure_message(result: &RunResults) -> String {
+    let mut parts = vec![];
+    if !result.fmt {
+        parts.push("cargo fmt --check failed".to_string());
+    }
+    if !result.clippy {
+        parts.push(format!(
+            "cargo clippy failed ({} diagnostics)",
+            result.clippy_diagnostics.len()
+        ));
+    }
+    if !result.test {
+        parts.push(format!(
+            "cargo test failed ({} diagnostics)",
+            result.test_diagnostics.len()
+        ));
+    }
+    parts.join("; ")
+}
+
+/// Concatenate the captured stdout/stderr of every failing stage in `result`, for the JUnit
+/// `<failure>` body. This is what makes a failure actionable instead of an opaque `false`: a
+/// `cargo test` assertion failure, for example, has no `--message-format=json` diagnostics at
+/// all, so the raw output is the only detail available.
+fn failure_details(result: &RunResults) -> String {
+    let mut sections = vec![];
+    if !result.fmt {
+        sections.push(captured_section(
+            "cargo fmt --all -- --check",
+            &result.fmt_output,
+        ));
+    }
+    if !result.clippy {
+        sections.push(captured_section("cargo clippy", &result.clippy_output));
+    }
+    if !result.test {
+        sections.push(captured_section("cargo test", &result.test_output));
+    }
+    sections.join("\n\n")
+}
+
+/// Render one `$ <command>` section with its captured stdout/stderr for [`failure_details`].
+fn captured_section(command: &str, output: &CapturedOutput) -> String {
+    format!("$ {command}\n{}{}", output.stdout, output.stderr)
+}
+
+/// Escape `value` for use as JUnit XML text/attribute content.
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_diagnostics_keeps_only_compiler_messages() {
+        let stdout = [
+            r#"{"reason":"compiler-artifact","package_id":"loco-rs"}"#,
+            r#"{"reason":"compiler-message","message":{"level":"warning","message":"unused import","spans":[{"file_name":"src/lib.rs","line_start":3,"column_start":5}]}}"#,
+            r#"{"reason":"build-finished","success":true}"#,
+        ]
+        .join("\n");
+
+        let diagnostics = parse_diagnostics(&stdout);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].level, "warning");
+        assert_eq!(diagnostics[0].message, "unused import");
+        assert_eq!(diagnostics[0].spans[0].file_name, "src/lib.rs");
+        assert_eq!(diagnostics[0].spans[0].line_start, 3);
+        assert_eq!(diagnostics[0].spans[0].column_start, 5);
+    }
+
+    #[test]
+    fn parse_diagnostics_skips_non_json_lines() {
+        let stdout = [
+            "running 3 tests",
+            r#"{"reason":"compiler-message","message":{"level":"error","message":"mismatched types","spans":[]}}"#,
+            "test result: FAILED. 2 passed; 1 failed",
+        ]
+        .join("\n");
+
+        let diagnostics = parse_diagnostics(&stdout);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].level, "error");
+    }
+
+    #[test]
+    fn parse_diagnostics_returns_empty_for_plain_test_output() {
+        let stdout = "running 1 test\ntest it_works ... FAILED\n";
+
+        assert!(parse_diagnostics(stdout).is_empty());
+    }
+
+    #[test]
+    fn xml_escape_escapes_reserved_characters() {
+        assert_eq!(
+            xml_escape(r#"<tag a="b"> & </tag>"#),
+            "&lt;tag a=&quot;b&quot;&gt; &amp; &lt;/tag&gt;"
+        );
+    }
+
+    #[test]
+    fn xml_escape_leaves_plain_text_untouched() {
+        assert_eq!(
+            xml_escape("no special characters here"),
+            "no special characters here"
+        );
+    }
 }